@@ -0,0 +1,217 @@
+//! A small render graph that replaces the fixed "decode -> convert -> transform -> compose"
+//! pipeline [`super::videos::InputVideo`] used to hardwire in `upload_data`.
+//!
+//! Instead of the per-input pipeline being a flat `Vec` of transformations, each stage (colour
+//! conversion, an individual transformation, composition, readback, ...) is a [Node] that declares
+//! the texture handles it reads from and writes to. A [Graph] built from a set of nodes is
+//! topologically sorted once and then executed against a shared `wgpu::CommandEncoder`, pulling its
+//! transient textures from a [TexturePool] rather than allocating a fresh `RGBATexture` per stage
+//! per frame.
+
+use std::collections::{HashMap, HashSet};
+
+pub use super::texture_pool::TexturePool;
+use super::textures::RGBATexture;
+
+/// A handle identifying one of a [Graph]'s transient textures. Handles are only meaningful within
+/// the [Graph] that produced them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextureHandle(usize);
+
+/// The execution context a [Node] is given when it runs: the encoder to record into, and the set
+/// of textures produced so far, addressable by [TextureHandle].
+pub struct RenderContext<'a> {
+    pub device: &'a wgpu::Device,
+    pub queue: &'a wgpu::Queue,
+    pub encoder: &'a mut wgpu::CommandEncoder,
+    textures: &'a HashMap<TextureHandle, RGBATexture>,
+}
+
+impl<'a> RenderContext<'a> {
+    /// Look up a texture produced by a node this one declared as an input.
+    pub fn texture(&self, handle: TextureHandle) -> &RGBATexture {
+        self.textures
+            .get(&handle)
+            .expect("node read from a texture handle no prior node produced")
+    }
+}
+
+/// One stage of a render graph: a decode, a colour conversion, a single texture transformation, the
+/// final scene composition, or an output readback.
+pub trait Node {
+    /// The texture handles this node reads from. Used to order nodes topologically.
+    fn inputs(&self) -> &[TextureHandle];
+
+    /// The texture handle this node writes to, if it produces a texture for later nodes to consume.
+    fn output(&self) -> Option<TextureHandle>;
+
+    /// The `(width, height)` of the texture this node produces, used to acquire it from the pool.
+    fn output_size(&self) -> (u32, u32);
+
+    /// Record this node's work into `ctx`, writing into `output` (the texture the graph acquired for
+    /// `self.output()`, if any), which the graph then stores under that handle for downstream nodes.
+    fn execute(&self, ctx: &mut RenderContext, output: Option<&RGBATexture>);
+}
+
+/// A render graph: an unordered set of [Node]s plus the dependency edges implied by their declared
+/// inputs/outputs, executed in topological order against a shared command encoder.
+///
+/// Generic over `'a` because nodes typically borrow from their owning [super::videos::InputVideo]
+/// (e.g. the texture transformers and uniforms they run), rather than owning that data themselves.
+pub struct Graph<'a> {
+    nodes: Vec<Box<dyn Node + 'a>>,
+    next_handle: usize,
+}
+
+impl<'a> Default for Graph<'a> {
+    fn default() -> Self {
+        Self {
+            nodes: Vec::new(),
+            next_handle: 0,
+        }
+    }
+}
+
+impl<'a> Graph<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allocate a fresh, unique texture handle for a node to declare as its output.
+    pub fn new_handle(&mut self) -> TextureHandle {
+        let handle = TextureHandle(self.next_handle);
+        self.next_handle += 1;
+        handle
+    }
+
+    /// Add `node` to the graph. Nodes may be added in any order; [Graph::execute] orders them by
+    /// their declared input/output dependencies.
+    pub fn add_node(&mut self, node: impl Node + 'a) {
+        self.nodes.push(Box::new(node));
+    }
+
+    /// Topologically sort the graph's nodes so that every node runs after all nodes producing its
+    /// inputs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the graph contains a cycle.
+    fn sorted_nodes(&self) -> Vec<&dyn Node> {
+        let producer_of: HashMap<TextureHandle, usize> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(i, node)| node.output().map(|handle| (handle, i)))
+            .collect();
+
+        let mut visited = vec![false; self.nodes.len()];
+        let mut in_progress = vec![false; self.nodes.len()];
+        let mut order = Vec::with_capacity(self.nodes.len());
+
+        fn visit(
+            i: usize,
+            nodes: &[Box<dyn Node + '_>],
+            producer_of: &HashMap<TextureHandle, usize>,
+            visited: &mut [bool],
+            in_progress: &mut [bool],
+            order: &mut Vec<usize>,
+        ) {
+            if visited[i] {
+                return;
+            }
+            assert!(!in_progress[i], "render graph contains a cycle");
+            in_progress[i] = true;
+
+            for input in nodes[i].inputs() {
+                if let Some(&producer) = producer_of.get(input) {
+                    visit(producer, nodes, producer_of, visited, in_progress, order);
+                }
+            }
+
+            in_progress[i] = false;
+            visited[i] = true;
+            order.push(i);
+        }
+
+        let mut order_indices = Vec::with_capacity(self.nodes.len());
+        for i in 0..self.nodes.len() {
+            visit(
+                i,
+                &self.nodes,
+                &producer_of,
+                &mut visited,
+                &mut in_progress,
+                &mut order_indices,
+            );
+        }
+
+        order_indices
+            .into_iter()
+            .map(|i| self.nodes[i].as_ref())
+            .collect()
+    }
+
+    /// Run every node in dependency order, acquiring each node's output texture from `pool` and
+    /// releasing nodes' outputs back to `pool` once nothing downstream still needs them.
+    pub fn execute(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        pool: &mut TexturePool,
+        bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> HashMap<TextureHandle, RGBATexture> {
+        let order = self.sorted_nodes();
+
+        // How many remaining nodes still need to read a given handle, so we know when it's safe to
+        // hand a texture back to the pool.
+        let mut remaining_readers: HashMap<TextureHandle, usize> = HashMap::new();
+        for node in &order {
+            for input in node.inputs() {
+                *remaining_readers.entry(*input).or_insert(0) += 1;
+            }
+        }
+
+        let mut live: HashMap<TextureHandle, RGBATexture> = HashMap::new();
+        let mut results: HashMap<TextureHandle, RGBATexture> = HashMap::new();
+        let produced_handles: HashSet<TextureHandle> =
+            order.iter().filter_map(|node| node.output()).collect();
+
+        for node in order {
+            let output_texture = node.output().map(|_| {
+                let (width, height) = node.output_size();
+                pool.acquire(device, width, height, bind_group_layout)
+            });
+
+            {
+                let mut ctx = RenderContext {
+                    device,
+                    queue,
+                    encoder,
+                    textures: &live,
+                };
+                node.execute(&mut ctx, output_texture.as_ref());
+            }
+
+            if let (Some(handle), Some(texture)) = (node.output(), output_texture) {
+                live.insert(handle, texture);
+            }
+
+            for input in node.inputs() {
+                let count = remaining_readers.get_mut(input).expect("tracked above");
+                *count -= 1;
+                // Release textures back to the pool as soon as their last reader has run, unless
+                // they're a final output of the graph with no consumer (callers decide that).
+                if *count == 0 && produced_handles.contains(input) {
+                    if let Some(texture) = live.remove(input) {
+                        pool.release(texture);
+                    }
+                }
+            }
+        }
+
+        // Whatever is still live is a graph output (nothing downstream consumed it).
+        results.extend(live);
+        results
+    }
+}
@@ -0,0 +1,99 @@
+//! A cache of scratch [RGBATexture]s, so that per-frame render graph execution (see
+//! [super::render_graph]) reuses textures across frames instead of allocating and tearing down a
+//! new one for every stage of every input on every frame.
+
+use std::collections::{HashMap, VecDeque};
+
+use super::textures::RGBATexture;
+
+/// A pool of [RGBATexture]s keyed by `(width, height)`. Textures are handed out with
+/// [TexturePool::acquire] and returned with [TexturePool::release]; once the number of retained,
+/// unused textures exceeds `capacity`, the least-recently-touched size bucket is evicted first.
+pub struct TexturePool {
+    capacity: usize,
+    free: HashMap<(u32, u32), Vec<RGBATexture>>,
+    /// Sizes ordered from least- to most-recently touched, used to pick an eviction victim.
+    recency: VecDeque<(u32, u32)>,
+}
+
+impl TexturePool {
+    /// Create a pool that retains at most `capacity` unused textures at a time.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            free: HashMap::new(),
+            recency: VecDeque::new(),
+        }
+    }
+
+    /// Take a `width`x`height` texture from the pool, allocating a new one via `RGBATexture::new`
+    /// if none is free.
+    pub fn acquire(
+        &mut self,
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> RGBATexture {
+        self.touch(width, height);
+
+        let size = (width, height);
+        let popped = match self.free.get_mut(&size) {
+            Some(textures) => {
+                let texture = textures.pop();
+                // Don't leave a zero-length `Vec` behind in `free` once its last texture is taken.
+                if textures.is_empty() {
+                    self.free.remove(&size);
+                }
+                texture
+            }
+            None => None,
+        };
+
+        popped.unwrap_or_else(|| RGBATexture::new(device, width, height, bind_group_layout))
+    }
+
+    /// Return a texture to the pool once its frame's passes are done with it, making it available
+    /// for the next [TexturePool::acquire] of the same size.
+    pub fn release(&mut self, texture: RGBATexture) {
+        let size = (texture.width(), texture.height());
+        self.touch(size.0, size.1);
+        self.free.entry(size).or_default().push(texture);
+        self.evict_if_over_capacity();
+    }
+
+    fn touch(&mut self, width: u32, height: u32) {
+        self.recency.retain(|&size| size != (width, height));
+        self.recency.push_back((width, height));
+    }
+
+    fn retained_count(&self) -> usize {
+        self.free.values().map(Vec::len).sum()
+    }
+
+    /// Evict textures from the coldest size bucket(s) until we're back at or under `capacity`.
+    fn evict_if_over_capacity(&mut self) {
+        while self.retained_count() > self.capacity {
+            let Some(&coldest) = self.recency.front() else {
+                break;
+            };
+
+            match self.free.get_mut(&coldest) {
+                Some(textures) if !textures.is_empty() => {
+                    textures.pop();
+                    if textures.is_empty() {
+                        self.free.remove(&coldest);
+                        self.recency.pop_front();
+                    }
+                }
+                _ => {
+                    // Either there's no bucket for `coldest`, or (shouldn't normally happen, since
+                    // `acquire`/the arm above both remove an empty bucket immediately) it's already
+                    // empty — either way there's nothing left to evict from it.
+                    self.free.remove(&coldest);
+                    self.recency.pop_front();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,227 @@
+//! A tiny WGSL preprocessor run at shader build time, so that texture transformation shaders can
+//! share a common prelude (vertex passthrough, sampler bindings, YUV/RGBA helpers) instead of each
+//! one carrying its own copy.
+//!
+//! Supports:
+//!  * `#include "name"` - spliced in from a [ShaderRegistry] of named snippets.
+//!  * `#define NAME` / `#ifdef NAME` / `#else` / `#endif` - simple conditional compilation.
+//!  * `{{name}}` - substituted with a value from the `constants` map, for things like bind group
+//!    indices or the output resolution.
+//!
+//! None of this is WGSL-aware: it operates line by line on the source text and hands the final,
+//! flattened string to `wgpu::Device::create_shader_module`.
+
+use std::collections::{HashMap, HashSet};
+
+/// A registry of named WGSL snippets that `#include` directives are resolved against.
+#[derive(Debug, Default, Clone)]
+pub struct ShaderRegistry {
+    snippets: HashMap<String, String>,
+}
+
+impl ShaderRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a snippet under `name`, so `#include "name"` resolves to its contents.
+    pub fn register(&mut self, name: impl Into<String>, source: impl Into<String>) {
+        self.snippets.insert(name.into(), source.into());
+    }
+
+    fn get(&self, name: &str) -> Option<&str> {
+        self.snippets.get(name).map(String::as_str)
+    }
+}
+
+/// Preprocess `source` against `registry`, expanding `#include`s and `#ifdef`s and substituting
+/// `{{constant}}` placeholders from `constants`, returning a flat WGSL source string.
+///
+/// # Panics
+///
+/// Panics if an `#include` names a snippet that isn't in `registry`, if `#include`s form a cycle,
+/// if an `#ifdef` block isn't closed with `#endif`, or if a `{{constant}}` placeholder has no
+/// entry in `constants`.
+pub fn preprocess(
+    source: &str,
+    registry: &ShaderRegistry,
+    constants: &HashMap<String, String>,
+) -> String {
+    let mut visited = HashSet::new();
+    let expanded = expand_includes(source, registry, &mut visited);
+    let conditioned = expand_conditionals(&expanded);
+    substitute_constants(&conditioned, constants)
+}
+
+/// `visited` tracks the chain of snippet names currently being expanded, so a snippet that
+/// (directly or transitively) `#include`s itself is caught as a cycle instead of recursing
+/// forever. Snippets may be registered at runtime (user-supplied transformation shaders), so this
+/// can't be assumed acyclic by construction.
+fn expand_includes(source: &str, registry: &ShaderRegistry, visited: &mut HashSet<String>) -> String {
+    let mut output = String::with_capacity(source.len());
+
+    for line in source.lines() {
+        if let Some(name) = line.trim().strip_prefix("#include") {
+            let name = name.trim().trim_matches('"');
+            let snippet = registry
+                .get(name)
+                .unwrap_or_else(|| panic!("no shader snippet registered under \"{name}\""));
+            assert!(
+                visited.insert(name.to_string()),
+                "circular #include: \"{name}\" includes itself, directly or transitively"
+            );
+            // Snippets may themselves `#include`, so resolve those too.
+            // The expansion already ends each of its lines with '\n'.
+            output.push_str(&expand_includes(snippet, registry, visited));
+            visited.remove(name);
+        } else {
+            output.push_str(line);
+            output.push('\n');
+        }
+    }
+
+    output
+}
+
+fn expand_conditionals(source: &str) -> String {
+    let mut defines: Vec<&str> = Vec::new();
+    // Whether each nesting level of `#ifdef` is currently active.
+    let mut active_stack: Vec<bool> = Vec::new();
+    let mut output = String::with_capacity(source.len());
+
+    for line in source.lines() {
+        let trimmed = line.trim();
+
+        if let Some(name) = trimmed.strip_prefix("#define") {
+            if active_stack.iter().all(|active| *active) {
+                defines.push(name.trim());
+            }
+        } else if let Some(name) = trimmed.strip_prefix("#ifdef") {
+            let parent_active = active_stack.last().copied().unwrap_or(true);
+            active_stack.push(parent_active && defines.contains(&name.trim()));
+        } else if trimmed == "#else" {
+            let current = active_stack
+                .last_mut()
+                .expect("#else without a matching #ifdef");
+            *current = !*current;
+        } else if trimmed == "#endif" {
+            active_stack
+                .pop()
+                .expect("#endif without a matching #ifdef");
+        } else if active_stack.iter().all(|active| *active) {
+            output.push_str(line);
+            output.push('\n');
+        }
+    }
+
+    assert!(
+        active_stack.is_empty(),
+        "unterminated #ifdef block: missing #endif"
+    );
+
+    output
+}
+
+fn substitute_constants(source: &str, constants: &HashMap<String, String>) -> String {
+    let mut output = String::with_capacity(source.len());
+    let mut rest = source;
+
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        let after_start = &rest[start + 2..];
+        let end = after_start
+            .find("}}")
+            .expect("unterminated {{constant}} placeholder");
+        let name = after_start[..end].trim();
+        let value = constants
+            .get(name)
+            .unwrap_or_else(|| panic!("no value provided for constant \"{name}\""));
+        output.push_str(value);
+        rest = &after_start[end + 2..];
+    }
+    output.push_str(rest);
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn else_branch_is_dropped_when_ifdef_is_defined() {
+        let source = "#define FOO\n#ifdef FOO\nkept\n#else\ndropped\n#endif\n";
+        assert_eq!(expand_conditionals(source), "kept\n");
+    }
+
+    #[test]
+    fn if_branch_is_dropped_when_ifdef_is_not_defined() {
+        let source = "#ifdef FOO\ndropped\n#else\nkept\n#endif\n";
+        assert_eq!(expand_conditionals(source), "kept\n");
+    }
+
+    #[test]
+    fn define_inside_a_dead_branch_does_not_take_global_effect() {
+        // FOO is defined, so the `#else` branch (and the `#define BAR` inside it) is dead;
+        // BAR must not end up defined for the later #ifdef.
+        let source =
+            "#define FOO\n#ifdef FOO\n#else\n#define BAR\n#endif\n#ifdef BAR\nkept\n#endif\n";
+        assert_eq!(expand_conditionals(source), "");
+    }
+
+    #[test]
+    fn define_inside_a_live_branch_does_take_effect() {
+        let source = "#define FOO\n#ifdef FOO\n#define BAR\n#endif\n#ifdef BAR\nkept\n#endif\n";
+        assert_eq!(expand_conditionals(source), "kept\n");
+    }
+
+    #[test]
+    fn nested_ifdef_requires_every_level_active() {
+        let source = "#define OUTER\n#ifdef OUTER\n#ifdef INNER\nkept\n#endif\n#endif\n";
+        assert_eq!(expand_conditionals(source), "");
+    }
+
+    #[test]
+    #[should_panic(expected = "unterminated #ifdef block")]
+    fn unterminated_ifdef_panics() {
+        expand_conditionals("#ifdef FOO\nkept\n");
+    }
+
+    #[test]
+    fn include_splices_in_a_registered_snippet() {
+        let mut registry = ShaderRegistry::new();
+        registry.register("prelude", "let x = 1;\n");
+        let mut visited = HashSet::new();
+        let output = expand_includes("#include \"prelude\"\nbody\n", &registry, &mut visited);
+        assert_eq!(output, "let x = 1;\nbody\n");
+    }
+
+    #[test]
+    fn include_resolves_transitively() {
+        let mut registry = ShaderRegistry::new();
+        registry.register("inner", "inner body\n");
+        registry.register("outer", "#include \"inner\"\n");
+        let mut visited = HashSet::new();
+        let output = expand_includes("#include \"outer\"\n", &registry, &mut visited);
+        assert_eq!(output, "inner body\n");
+    }
+
+    #[test]
+    #[should_panic(expected = "circular #include")]
+    fn self_including_snippet_panics_instead_of_recursing_forever() {
+        let mut registry = ShaderRegistry::new();
+        registry.register("a", "#include \"a\"\n");
+        let mut visited = HashSet::new();
+        expand_includes("#include \"a\"\n", &registry, &mut visited);
+    }
+
+    #[test]
+    #[should_panic(expected = "circular #include")]
+    fn mutually_including_snippets_panic_instead_of_recursing_forever() {
+        let mut registry = ShaderRegistry::new();
+        registry.register("a", "#include \"b\"\n");
+        registry.register("b", "#include \"a\"\n");
+        let mut visited = HashSet::new();
+        expand_includes("#include \"a\"\n", &registry, &mut visited);
+    }
+}
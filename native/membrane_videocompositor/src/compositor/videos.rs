@@ -4,6 +4,7 @@ use std::sync::Arc;
 use wgpu::util::DeviceExt;
 
 use super::colour_converters::YUVToRGBAConverter;
+use super::render_graph::{Graph, Node, RenderContext, TextureHandle, TexturePool};
 
 use super::texture_transformations::{
     texture_transformers::TextureTransformer, TextureTransformationName,
@@ -12,6 +13,65 @@ use super::texture_transformations::{
 use super::textures::{RGBATexture, YUVTextures};
 use super::{Vec2d, Vertex};
 
+/// A render graph [Node] that colour-converts this video's freshly uploaded YUV planes into RGBA.
+struct ConvertNode<'a> {
+    converter: &'a YUVToRGBAConverter,
+    yuv_textures: &'a YUVTextures,
+    output: TextureHandle,
+    size: (u32, u32),
+}
+
+impl<'a> Node for ConvertNode<'a> {
+    fn inputs(&self) -> &[TextureHandle] {
+        &[]
+    }
+
+    fn output(&self) -> Option<TextureHandle> {
+        Some(self.output)
+    }
+
+    fn output_size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    fn execute(&self, ctx: &mut RenderContext, output: Option<&RGBATexture>) {
+        let output = output.expect("ConvertNode always produces an output texture");
+        self.converter
+            .convert(ctx.device, ctx.queue, self.yuv_textures, output);
+    }
+}
+
+/// A render graph [Node] running a single [TextureTransformationUniform] over the texture produced
+/// by a prior node.
+struct TransformNode<'a> {
+    transformer: &'a TextureTransformer,
+    uniform: &'a TextureTransformationUniform,
+    input: TextureHandle,
+    output: TextureHandle,
+    size: (u32, u32),
+}
+
+impl<'a> Node for TransformNode<'a> {
+    fn inputs(&self) -> &[TextureHandle] {
+        std::slice::from_ref(&self.input)
+    }
+
+    fn output(&self) -> Option<TextureHandle> {
+        Some(self.output)
+    }
+
+    fn output_size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    fn execute(&self, ctx: &mut RenderContext, output: Option<&RGBATexture>) {
+        let output = output.expect("TransformNode always produces an output texture");
+        let input = ctx.texture(self.input);
+        self.transformer
+            .transform(ctx.device, ctx.queue, input, output, self.uniform);
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 // All of the fields are in pixels, except of the `z`, which should be from the <0, 1> range
 pub struct VideoProperties {
@@ -20,6 +80,106 @@ pub struct VideoProperties {
     /// of the scene this will be rendered onto will cause it to not be displayed.
     pub resolution: Vec2d<u32>,
     pub placement: VideoPlacement,
+    /// How this video's pixels are combined with whatever has already been composited
+    /// underneath it.
+    pub blend_mode: BlendMode,
+}
+
+/// A blend mode applied when compositing an [InputVideo] over the rest of the scene.
+///
+/// [BlendMode::Normal], [BlendMode::Multiply], [BlendMode::Screen], [BlendMode::Add],
+/// [BlendMode::Darken] and [BlendMode::Lighten] are separable: each output pixel only depends on
+/// the corresponding source and backdrop pixel, so they can be expressed as a fixed-function
+/// `wgpu::BlendState`. [BlendMode::Overlay] is non-separable and additionally needs the
+/// already-composited backdrop as a texture to sample in a blend shader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum BlendMode {
+    #[default]
+    Normal,
+    Multiply,
+    Screen,
+    Overlay,
+    Add,
+    Darken,
+    Lighten,
+}
+
+impl BlendMode {
+    /// Whether this blend mode can be expressed as a fixed-function `wgpu::BlendState`, as opposed
+    /// to needing the composited backdrop sampled in a shader.
+    pub fn is_separable(&self) -> bool {
+        !matches!(self, BlendMode::Overlay)
+    }
+
+    /// The `wgpu::BlendState` implementing this mode. Only meaningful for separable modes; use the
+    /// backdrop-sampling blend pass for [BlendMode::Overlay] instead.
+    pub fn blend_state(&self) -> wgpu::BlendState {
+        use wgpu::{BlendComponent, BlendFactor, BlendOperation, BlendState};
+
+        match self {
+            BlendMode::Normal => BlendState::ALPHA_BLENDING,
+
+            BlendMode::Multiply => BlendState {
+                color: BlendComponent {
+                    src_factor: BlendFactor::Dst,
+                    dst_factor: BlendFactor::OneMinusSrcAlpha,
+                    operation: BlendOperation::Add,
+                },
+                alpha: BlendComponent::OVER,
+            },
+
+            BlendMode::Screen => BlendState {
+                color: BlendComponent {
+                    src_factor: BlendFactor::One,
+                    dst_factor: BlendFactor::OneMinusSrc,
+                    operation: BlendOperation::Add,
+                },
+                alpha: BlendComponent::OVER,
+            },
+
+            BlendMode::Add => BlendState {
+                color: BlendComponent {
+                    src_factor: BlendFactor::One,
+                    dst_factor: BlendFactor::One,
+                    operation: BlendOperation::Add,
+                },
+                alpha: BlendComponent::OVER,
+            },
+
+            BlendMode::Darken => BlendState {
+                color: BlendComponent {
+                    src_factor: BlendFactor::One,
+                    dst_factor: BlendFactor::One,
+                    operation: BlendOperation::Min,
+                },
+                alpha: BlendComponent::OVER,
+            },
+
+            BlendMode::Lighten => BlendState {
+                color: BlendComponent {
+                    src_factor: BlendFactor::One,
+                    dst_factor: BlendFactor::One,
+                    operation: BlendOperation::Max,
+                },
+                alpha: BlendComponent::OVER,
+            },
+
+            BlendMode::Overlay => {
+                unreachable!("Overlay is non-separable and has no fixed-function BlendState")
+            }
+        }
+    }
+}
+
+/// The pipelines needed to draw an [InputVideo] with any [BlendMode]: one fixed-function pipeline
+/// per separable mode, plus a shader-based pass for non-separable modes that samples the
+/// already-composited backdrop.
+pub struct BlendPipelines<'a> {
+    /// One pipeline per separable [BlendMode], keyed by mode.
+    pub separable: &'a HashMap<BlendMode, wgpu::RenderPipeline>,
+    /// The pipeline for the backdrop-sampling blend pass used by non-separable modes. Its bind
+    /// group 1 is expected to hold the composited-so-far scene texture.
+    pub backdrop_blend: &'a wgpu::RenderPipeline,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -63,6 +223,65 @@ pub struct InputVideo {
     /// while it's frames are considered 'too new'. When the first frame from this video is composed,
     /// this gets set to `false` and the video operates normally.
     was_just_added: bool,
+    /// Set whenever this video's visible output could have changed since the last time it was
+    /// drawn: its front frame advanced, or its placement changed. Cleared by [InputVideo::take_damage].
+    dirty: bool,
+    /// A placement this video used to occupy that still needs to be unioned into the next damage
+    /// rectangle, because a moved/resized input leaves a stale region behind at its old position.
+    pending_damage: Option<DamageRect>,
+}
+
+/// An axis-aligned, screen-space rectangle in output pixels, as reported by [InputVideo::take_damage].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DamageRect {
+    pub position: Vec2d<u32>,
+    pub size: Vec2d<u32>,
+}
+
+impl DamageRect {
+    /// The smallest [DamageRect] containing both `self` and `other`.
+    pub fn union(&self, other: &DamageRect) -> DamageRect {
+        let min_x = self.position.x.min(other.position.x);
+        let min_y = self.position.y.min(other.position.y);
+        let max_x = (self.position.x + self.size.x).max(other.position.x + other.size.x);
+        let max_y = (self.position.y + self.size.y).max(other.position.y + other.size.y);
+
+        DamageRect {
+            position: Vec2d { x: min_x, y: min_y },
+            size: Vec2d {
+                x: max_x - min_x,
+                y: max_y - min_y,
+            },
+        }
+    }
+
+    /// Whether `self` and `other` overlap.
+    pub fn intersects(&self, other: &DamageRect) -> bool {
+        self.position.x < other.position.x + other.size.x
+            && other.position.x < self.position.x + self.size.x
+            && self.position.y < other.position.y + other.size.y
+            && other.position.y < self.position.y + self.size.y
+    }
+
+    /// The overlapping region of `self` and `other`, or `None` if they don't overlap.
+    pub fn intersect(&self, other: &DamageRect) -> Option<DamageRect> {
+        if !self.intersects(other) {
+            return None;
+        }
+
+        let min_x = self.position.x.max(other.position.x);
+        let min_y = self.position.y.max(other.position.y);
+        let max_x = (self.position.x + self.size.x).min(other.position.x + other.size.x);
+        let max_y = (self.position.y + self.size.y).min(other.position.y + other.size.y);
+
+        Some(DamageRect {
+            position: Vec2d { x: min_x, y: min_y },
+            size: Vec2d {
+                x: max_x - min_x,
+                y: max_y - min_y,
+            },
+        })
+    }
 }
 
 impl InputVideo {
@@ -109,6 +328,8 @@ impl InputVideo {
             previous_frame: None,
             single_texture_bind_group_layout,
             was_just_added: true,
+            dirty: true,
+            pending_damage: None,
         }
     }
 
@@ -129,6 +350,9 @@ impl InputVideo {
             Some(all_textures_bind_group_layout),
         );
         self.yuv_textures = yuv_textures;
+        // The old placement also needs to be repainted over, since whatever used to be drawn there
+        // is now stale.
+        let old_bounds = self.bounds();
         self.properties = properties;
         match texture_transformations {
             Some(mut texture_transformations) => {
@@ -144,6 +368,34 @@ impl InputVideo {
                 .to_vec();
             }
         }
+        self.pending_damage = Some(
+            self.pending_damage
+                .map_or(old_bounds, |pending| pending.union(&old_bounds)),
+        );
+        self.dirty = true;
+    }
+
+    /// The current screen-space bounding rectangle of this video, in output pixels.
+    pub fn bounds(&self) -> DamageRect {
+        DamageRect {
+            position: self.properties.placement.position,
+            size: self.properties.placement.size,
+        }
+    }
+
+    /// If this video's visible output could have changed since the last call, returns the
+    /// (possibly unioned) damage rectangle [InputVideo::draw] should redraw, and clears the dirty
+    /// flag. Returns `None` if nothing changed, so the caller can skip this video entirely.
+    pub fn take_damage(&mut self) -> Option<DamageRect> {
+        if !self.dirty {
+            return None;
+        }
+        self.dirty = false;
+        let current = self.bounds();
+        Some(match self.pending_damage.take() {
+            Some(pending) => pending.union(&current),
+            None => current,
+        })
     }
 
     pub fn update_texture_transformations(
@@ -159,6 +411,9 @@ impl InputVideo {
         texture_transformations
     }
 
+    /// Builds and runs the per-frame render graph: colour conversion, then each of
+    /// `self.texture_transformations` in order. Nodes pull their scratch textures from
+    /// `texture_pool` instead of allocating a fresh [RGBATexture] per stage.
     #[allow(clippy::too_many_arguments)]
     pub fn upload_data(
         &mut self,
@@ -169,49 +424,72 @@ impl InputVideo {
         pts: u64,
         last_rendered_pts: Option<u64>,
         texture_transformers: &HashMap<TextureTransformationName, TextureTransformer>,
+        texture_pool: &mut TexturePool,
     ) {
         self.yuv_textures.upload_data(queue, data);
-        let mut frame = RGBATexture::new(
-            device,
-            self.properties.resolution.x,
-            self.properties.resolution.y,
-            &self.single_texture_bind_group_layout,
-        );
-        converter.convert(device, queue, &self.yuv_textures, &frame);
-
-        // Runs all texture transformations.
-        for transformation_uniform in self.texture_transformations.iter() {
-            let transformed_frame = RGBATexture::new(
-                device,
-                self.properties.resolution.x,
-                self.properties.resolution.y,
-                &self.single_texture_bind_group_layout,
-            );
 
-            let texture_transformer =
-                transformation_uniform.get_texture_transformer(texture_transformers);
+        let size = (self.properties.resolution.x, self.properties.resolution.y);
 
-            texture_transformer.transform(
-                device,
-                queue,
-                &frame,
-                &transformed_frame,
-                transformation_uniform,
-            );
+        let mut graph = Graph::new();
+        let convert_output = graph.new_handle();
+        graph.add_node(ConvertNode {
+            converter,
+            yuv_textures: &self.yuv_textures,
+            output: convert_output,
+            size,
+        });
 
-            frame = transformed_frame;
+        let resolved_transformers: Vec<&TextureTransformer> = self
+            .texture_transformations
+            .iter()
+            .map(|uniform| uniform.get_texture_transformer(texture_transformers))
+            .collect();
+
+        let mut previous = convert_output;
+        for (transformer, uniform) in resolved_transformers
+            .into_iter()
+            .zip(self.texture_transformations.iter())
+        {
+            let output = graph.new_handle();
+            graph.add_node(TransformNode {
+                transformer,
+                uniform,
+                input: previous,
+                output,
+                size,
+            });
+            previous = output;
         }
 
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("input video render graph encoder"),
+        });
+
+        let mut outputs = graph.execute(
+            device,
+            queue,
+            &mut encoder,
+            texture_pool,
+            &self.single_texture_bind_group_layout,
+        );
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let frame = outputs
+            .remove(&previous)
+            .expect("render graph always produces the final node's output");
+
         // if we haven't rendered a frame yet, or pts of our frame is ahead of last rendered frame
         if last_rendered_pts.is_none() || pts > last_rendered_pts.unwrap() {
             // then we can add the frame to the queue (we assume the frames come in order)
             self.frames.push_back(Message::Frame { frame, pts });
+            self.dirty = true;
         }
         // otherwise, our frame is too old to be added to the queue, so we check if it is newer than the previously used frame,
         // which is our fallback in case we are forced to render before a new enough frame arrives.
         else if let Some(Message::Frame { pts: prev_pts, .. }) = self.previous_frame.as_ref() {
             if *prev_pts < pts {
                 self.previous_frame = Some(Message::Frame { frame, pts });
+                self.dirty = true;
             }
         }
     }
@@ -279,20 +557,30 @@ impl InputVideo {
         &self.properties
     }
 
-    /// This returns pts of the used frame
+    /// This returns pts of the used frame.
+    ///
+    /// `damage_region` is the compositor's union of every *other* input's damage this frame (see
+    /// [InputVideo::take_damage]). Returns [DrawResult::NotRendered] without touching
+    /// `render_pass` if neither this video's own output changed nor `damage_region` overlaps
+    /// its bounds — an input that hasn't itself changed still needs to be redrawn if another
+    /// input's damage exposes or covers part of it (e.g. a video that moved away uncovers this one
+    /// underneath it). Otherwise restricts drawing to the combined damaged region via
+    /// `render_pass.set_scissor_rect`, rather than recompositing the whole output for a change that
+    /// only affects part of it.
+    ///
+    /// `blend_pipelines` provides the pipeline for this video's [BlendMode]. For a non-separable
+    /// mode, `backdrop_bind_group` must hold the scene composited so far, since the blend shader
+    /// needs to sample it; it is unused for separable modes.
     pub fn draw<'a>(
         &'a mut self,
         queue: &wgpu::Queue,
         render_pass: &mut wgpu::RenderPass<'a>,
         output_caps: &crate::RawVideo,
         frame_interval: Option<(u64, u64)>,
+        blend_pipelines: &'a BlendPipelines,
+        backdrop_bind_group: Option<&'a wgpu::BindGroup>,
+        damage_region: Option<&DamageRect>,
     ) -> DrawResult {
-        queue.write_buffer(
-            &self.vertices,
-            0,
-            bytemuck::cast_slice(&self.vertex_data(output_caps)),
-        );
-
         let (frame, pts) = match self.frames.front() {
             Some(Message::Frame { frame, pts }) => {
                 // this is the case when the video was just added and its frames are 'too new'
@@ -316,6 +604,68 @@ impl InputVideo {
             },
         };
 
+        let own_damage = self.take_damage();
+        let bounds = self.bounds();
+        let is_exposed = damage_region.is_some_and(|region| region.intersects(&bounds));
+
+        // Neither this video's own output changed, nor does another input's damage overlap it, so
+        // there's nothing here that needs to be redrawn; reuse whatever is already in the output.
+        if own_damage.is_none() && !is_exposed {
+            return DrawResult::NotRendered;
+        }
+
+        let damage = match (own_damage, is_exposed) {
+            (Some(own), true) => own.union(damage_region.unwrap()),
+            (Some(own), false) => own,
+            (None, true) => *damage_region.unwrap(),
+            (None, false) => unreachable!("checked above"),
+        };
+
+        // Only reupload the vertex buffer once a video is actually going to be drawn, rather than
+        // on every call regardless of whether anything changed.
+        queue.write_buffer(
+            &self.vertices,
+            0,
+            bytemuck::cast_slice(&self.vertex_data(output_caps)),
+        );
+
+        let output_bounds = DamageRect {
+            position: Vec2d { x: 0, y: 0 },
+            size: Vec2d {
+                x: output_caps.width.get(),
+                y: output_caps.height.get(),
+            },
+        };
+
+        // Restrict drawing to the damaged region (clipped to the output), rather than
+        // recompositing the whole frame for a change that only touched part of it.
+        let Some(scissor) = damage.intersect(&output_bounds) else {
+            return DrawResult::NotRendered;
+        };
+        render_pass.set_scissor_rect(
+            scissor.position.x,
+            scissor.position.y,
+            scissor.size.x,
+            scissor.size.y,
+        );
+
+        if self.properties.blend_mode.is_separable() {
+            render_pass.set_pipeline(
+                blend_pipelines
+                    .separable
+                    .get(&self.properties.blend_mode)
+                    .expect("a pipeline for every separable BlendMode should have been built"),
+            );
+        } else {
+            render_pass.set_pipeline(blend_pipelines.backdrop_blend);
+            render_pass.set_bind_group(
+                1,
+                backdrop_bind_group
+                    .expect("non-separable blend modes need the composited backdrop bound"),
+                &[],
+            );
+        }
+
         render_pass.set_bind_group(0, frame.texture.bind_group.as_ref().unwrap(), &[]);
 
         render_pass.set_index_buffer(self.indices.slice(..), wgpu::IndexFormat::Uint16);
@@ -339,6 +689,7 @@ impl InputVideo {
     pub fn pop_frame(&mut self) {
         if let Some(Message::Frame { pts, frame }) = self.frames.pop_front() {
             self.previous_frame = Some(Message::Frame { pts, frame });
+            self.dirty = true;
         }
     }
 
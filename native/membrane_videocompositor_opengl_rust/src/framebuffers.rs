@@ -118,11 +118,79 @@ impl Drop for FramebufferObject {
     }
 }
 
-/// A render target suitable for rendering YUV420p frames.
-/// Because this is a planar format in which not all planes have the same resolution, the rendering has to be done separately for each frame.
-/// That is why we have 3 separate framebuffers in this struct.
+/// Describes the plane layout of a YUV pixel format: how many planes it has, the internal/output
+/// formats and type of each one, and where each plane starts in a buffer produced by
+/// [`YUVRenderTarget::read`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// Planar 4:2:0: separate `Y`, `U` and `V` planes, chroma planes at half resolution.
+    I420,
+    /// Planar 4:2:2: separate `Y`, `U` and `V` planes, chroma planes at half horizontal resolution only.
+    I422,
+    /// Planar 4:4:4: separate `Y`, `U` and `V` planes, all at full resolution.
+    I444,
+    /// Semi-planar 4:2:0: a full-resolution `Y` plane followed by a single half-resolution plane
+    /// with interleaved `U`/`V` samples.
+    NV12,
+}
+
+impl PixelFormat {
+    /// The number of planes this format is made of.
+    fn plane_count(&self) -> usize {
+        match self {
+            PixelFormat::I420 | PixelFormat::I422 | PixelFormat::I444 => 3,
+            PixelFormat::NV12 => 2,
+        }
+    }
+
+    /// The dimensions, in pixels, of `plane` for an image with the given Y-plane `width` and `height`.
+    fn plane_dimensions(&self, plane: usize, width: usize, height: usize) -> (usize, usize) {
+        match (self, plane) {
+            (_, 0) => (width, height),
+            (PixelFormat::I420, _) => (width / 2, height / 2),
+            (PixelFormat::I422, _) => (width / 2, height),
+            (PixelFormat::I444, _) => (width, height),
+            (PixelFormat::NV12, 1) => (width / 2, height / 2),
+            _ => unreachable!("plane index out of range for this format"),
+        }
+    }
+
+    /// The internal, output format and output type OpenGL should use for `plane`.
+    fn plane_gl_formats(&self, plane: usize) -> (gl::GLenum, gl::GLenum, gl::GLenum) {
+        match (self, plane) {
+            (PixelFormat::NV12, 1) => (gl::RG8, gl::RG, gl::UNSIGNED_BYTE),
+            _ => (gl::R8, gl::RED, gl::UNSIGNED_BYTE),
+        }
+    }
+
+    /// The byte offset, into a buffer produced by [`YUVRenderTarget::read`], at which `plane` starts.
+    fn plane_offset(&self, plane: usize, width: usize, height: usize) -> usize {
+        let mut offset = 0;
+        for i in 0..plane {
+            let (plane_width, plane_height) = self.plane_dimensions(i, width, height);
+            let bytes_per_pixel = match self.plane_gl_formats(i) {
+                (gl::RG8, _, _) => 2,
+                _ => 1,
+            };
+            offset += plane_width * plane_height * bytes_per_pixel;
+        }
+        offset
+    }
+
+    /// The total size, in bytes, of a buffer able to hold all planes of an image with the given
+    /// Y-plane `width` and `height`.
+    fn total_size(&self, width: usize, height: usize) -> usize {
+        self.plane_offset(self.plane_count(), width, height)
+    }
+}
+
+/// A render target suitable for rendering YUV frames in one of several [`PixelFormat`]s.
+/// Because these are planar (or semi-planar) formats in which not all planes share the same
+/// resolution or channel count, the rendering has to be done separately for each plane.
+/// That is why we have a separate framebuffer per plane in this struct.
 pub struct YUVRenderTarget {
-    framebuffers: [FramebufferObject; 3],
+    framebuffers: Vec<FramebufferObject>,
+    format: PixelFormat,
     width: usize,
     height: usize,
     bound_plane: Option<Plane>,
@@ -130,14 +198,26 @@ pub struct YUVRenderTarget {
 
 impl YUVRenderTarget {
     /// Create a new instance.
-    /// `width` and `height` should be the dimensions of the Y plane in pixels
-    pub fn new(width: usize, height: usize) -> Result<Self, CompositorError> {
+    /// `width` and `height` should be the dimensions of the Y plane in pixels.
+    /// `format` determines the plane count, dimensions and OpenGL formats used.
+    pub fn new(width: usize, height: usize, format: PixelFormat) -> Result<Self, CompositorError> {
+        let framebuffers = (0..format.plane_count())
+            .map(|plane| {
+                let (plane_width, plane_height) = format.plane_dimensions(plane, width, height);
+                let (internal_format, output_format, output_type) = format.plane_gl_formats(plane);
+                FramebufferObject::new(
+                    plane_width,
+                    plane_height,
+                    internal_format,
+                    output_format,
+                    output_type,
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
         Ok(Self {
-            framebuffers: [
-                FramebufferObject::new(width, height, gl::R8, gl::RED, gl::UNSIGNED_BYTE)?,
-                FramebufferObject::new(width / 2, height / 2, gl::R8, gl::RED, gl::UNSIGNED_BYTE)?,
-                FramebufferObject::new(width / 2, height / 2, gl::R8, gl::RED, gl::UNSIGNED_BYTE)?,
-            ],
+            framebuffers,
+            format,
             width,
             height,
             bound_plane: None,
@@ -149,7 +229,7 @@ impl YUVRenderTarget {
         &mut self,
         plane: Plane,
     ) -> Result<DrawBoundYUVRenderTarget, CompositorError> {
-        self.framebuffers[plane as usize].bind_for_drawing()?;
+        self.framebuffers[plane.index(self.format)].bind_for_drawing()?;
         self.bound_plane = Some(plane);
         Ok(DrawBoundYUVRenderTarget { target: self })
     }
@@ -160,15 +240,13 @@ impl YUVRenderTarget {
     ///
     /// Panics if the buffer is not long enough for the contents to fit.
     pub fn read(&self, buffer: &mut [u8]) -> Result<(), CompositorError> {
-        let pixels_amount = self.width * self.height;
-        assert!(buffer.len() >= pixels_amount * 3 / 2); // FIXME: This should return an error instead of panicking
+        assert!(buffer.len() >= self.format.total_size(self.width, self.height)); // FIXME: This should return an error instead of panicking
 
         unsafe {
-            self.framebuffers[0].read_to_ptr(buffer.as_mut_ptr())?;
-
-            self.framebuffers[1].read_to_ptr(buffer.as_mut_ptr().add(pixels_amount))?;
-
-            self.framebuffers[2].read_to_ptr(buffer.as_mut_ptr().add(pixels_amount * 5 / 4))?;
+            for (plane, framebuffer) in self.framebuffers.iter().enumerate() {
+                let offset = self.format.plane_offset(plane, self.width, self.height);
+                framebuffer.read_to_ptr(buffer.as_mut_ptr().add(offset))?;
+            }
         }
 
         Ok(())
@@ -183,6 +261,11 @@ impl YUVRenderTarget {
     pub fn height(&self) -> usize {
         self.height
     }
+
+    /// Get the pixel format this render target was created with.
+    pub fn format(&self) -> PixelFormat {
+        self.format
+    }
 }
 
 pub struct DrawBoundYUVRenderTarget<'a> {
@@ -191,16 +274,95 @@ pub struct DrawBoundYUVRenderTarget<'a> {
 
 impl<'a> Drop for DrawBoundYUVRenderTarget<'a> {
     fn drop(&mut self) {
-        self.target.framebuffers[self.target.bound_plane.unwrap() as usize].unbind_drawing();
+        let format = self.target.format;
+        self.target.framebuffers[self.target.bound_plane.unwrap().index(format)].unbind_drawing();
         self.target.bound_plane = None;
     }
 }
 
-/// Represents a plane in a YUV planar image format.
-#[repr(usize)]
+/// Represents a plane in a YUV image format.
+///
+/// For semi-planar formats (e.g. [`PixelFormat::NV12`]) `U` and `V` both refer to the same
+/// interleaved chroma plane; use [`Plane::index`] to resolve the actual framebuffer index for a
+/// given [`PixelFormat`].
 #[derive(Debug, Clone, Copy)]
 pub enum Plane {
-    Y = 0,
+    Y,
     U,
     V,
 }
+
+impl Plane {
+    /// Resolve this plane to a framebuffer index within a [`YUVRenderTarget`] of the given `format`.
+    fn index(self, format: PixelFormat) -> usize {
+        match (format, self) {
+            (_, Plane::Y) => 0,
+            (PixelFormat::NV12, Plane::U | Plane::V) => 1,
+            (_, Plane::U) => 1,
+            (_, Plane::V) => 2,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plane_count_matches_planar_vs_semi_planar() {
+        assert_eq!(PixelFormat::I420.plane_count(), 3);
+        assert_eq!(PixelFormat::I422.plane_count(), 3);
+        assert_eq!(PixelFormat::I444.plane_count(), 3);
+        assert_eq!(PixelFormat::NV12.plane_count(), 2);
+    }
+
+    #[test]
+    fn plane_dimensions_subsample_chroma_per_format() {
+        assert_eq!(PixelFormat::I420.plane_dimensions(0, 160, 120), (160, 120));
+        assert_eq!(PixelFormat::I420.plane_dimensions(1, 160, 120), (80, 60));
+        assert_eq!(PixelFormat::I420.plane_dimensions(2, 160, 120), (80, 60));
+
+        assert_eq!(PixelFormat::I422.plane_dimensions(1, 160, 120), (80, 120));
+
+        assert_eq!(PixelFormat::I444.plane_dimensions(1, 160, 120), (160, 120));
+
+        assert_eq!(PixelFormat::NV12.plane_dimensions(0, 160, 120), (160, 120));
+        assert_eq!(PixelFormat::NV12.plane_dimensions(1, 160, 120), (80, 60));
+    }
+
+    #[test]
+    fn plane_offset_accounts_for_nv12s_interleaved_two_byte_plane() {
+        // Y plane (160*120 bytes), then a half-resolution RG8 plane (2 bytes/sample).
+        assert_eq!(PixelFormat::NV12.plane_offset(0, 160, 120), 0);
+        assert_eq!(PixelFormat::NV12.plane_offset(1, 160, 120), 160 * 120);
+        // The end of the chroma plane: 80x60 samples of 2 bytes each after the Y plane.
+        assert_eq!(
+            PixelFormat::NV12.plane_offset(2, 160, 120),
+            160 * 120 + 80 * 60 * 2
+        );
+    }
+
+    #[test]
+    fn total_size_matches_one_and_a_half_bytes_per_pixel_for_4_2_0_formats() {
+        // Both I420 (three 1-byte planes) and NV12 (one 1-byte plane, one 2-byte half-res plane)
+        // land on the same total: 1 + 0.25 + 0.25 bytes per pixel.
+        let expected = 160 * 120 * 3 / 2;
+        assert_eq!(PixelFormat::I420.total_size(160, 120), expected);
+        assert_eq!(PixelFormat::NV12.total_size(160, 120), expected);
+    }
+
+    #[test]
+    fn total_size_full_resolution_for_i444() {
+        assert_eq!(PixelFormat::I444.total_size(160, 120), 160 * 120 * 3);
+    }
+
+    #[test]
+    fn plane_index_maps_nv12s_u_and_v_onto_the_same_interleaved_plane() {
+        assert_eq!(Plane::Y.index(PixelFormat::NV12), 0);
+        assert_eq!(Plane::U.index(PixelFormat::NV12), 1);
+        assert_eq!(Plane::V.index(PixelFormat::NV12), 1);
+
+        assert_eq!(Plane::U.index(PixelFormat::I420), 1);
+        assert_eq!(Plane::V.index(PixelFormat::I420), 2);
+    }
+}
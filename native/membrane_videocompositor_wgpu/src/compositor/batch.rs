@@ -0,0 +1,132 @@
+//! A growable batch of quads, modeled on the reui `Batch` type: accumulate every visible input's
+//! geometry into one vertex buffer and one index buffer per frame, bind them once with [Batch::bind],
+//! then issue one `draw_indexed` range per input with a `base_vertex` offset, instead of rebinding a
+//! separate vertex/index buffer per input per plane.
+
+use wgpu::util::DeviceExt;
+
+use super::Vertex;
+
+#[rustfmt::skip]
+const QUAD_INDICES: [u16; 6] = [
+    0, 1, 3,
+    1, 2, 3
+];
+
+/// The range within a [Batch] that one pushed quad occupies, returned by [Batch::push_quad] so the
+/// caller can later issue a `draw_indexed` call for just that quad.
+#[derive(Debug, Clone, Copy)]
+pub struct BatchEntry {
+    pub base_vertex: i32,
+    pub index_range: std::ops::Range<u32>,
+}
+
+/// Accumulates quads for a frame. Call [Batch::clear] at the start of each frame, [Batch::push_quad]
+/// once per visible input, then [Batch::upload] before drawing any of the returned [BatchEntry]s.
+#[derive(Default)]
+pub struct Batch {
+    vertices: Vec<Vertex>,
+    indices: Vec<u16>,
+    vertex_buffer: Option<wgpu::Buffer>,
+    index_buffer: Option<wgpu::Buffer>,
+}
+
+impl Batch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn base_vertex(&self) -> i32 {
+        self.vertices.len() as i32
+    }
+
+    fn base_index(&self) -> u32 {
+        self.indices.len() as u32
+    }
+
+    /// Add one quad's vertices to the batch, returning the entry needed to draw just this quad
+    /// after the next [Batch::upload].
+    pub fn push_quad(&mut self, vertices: [Vertex; 4]) -> BatchEntry {
+        let base_vertex = self.base_vertex();
+        let base_index = self.base_index();
+
+        self.vertices.extend_from_slice(&vertices);
+        self.indices.extend_from_slice(&QUAD_INDICES);
+
+        BatchEntry {
+            base_vertex,
+            index_range: base_index..base_index + QUAD_INDICES.len() as u32,
+        }
+    }
+
+    /// Drop this frame's accumulated geometry, ready for the next frame's inputs to be pushed.
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+        self.indices.clear();
+    }
+
+    /// Upload the batch's accumulated geometry into its vertex/index buffers, (re)creating them if
+    /// they're not big enough yet. Must be called after all of a frame's [Batch::push_quad] calls
+    /// and before drawing any of their [BatchEntry]s.
+    pub fn upload(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        let vertex_bytes = bytemuck::cast_slice(&self.vertices);
+        match &self.vertex_buffer {
+            Some(buffer) if buffer.size() >= vertex_bytes.len() as u64 => {
+                queue.write_buffer(buffer, 0, vertex_bytes);
+            }
+            _ => {
+                self.vertex_buffer = Some(device.create_buffer_init(
+                    &wgpu::util::BufferInitDescriptor {
+                        label: Some("batch vertex buffer"),
+                        contents: vertex_bytes,
+                        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                    },
+                ));
+            }
+        }
+
+        let index_bytes = bytemuck::cast_slice(&self.indices);
+        match &self.index_buffer {
+            Some(buffer) if buffer.size() >= index_bytes.len() as u64 => {
+                queue.write_buffer(buffer, 0, index_bytes);
+            }
+            _ => {
+                self.index_buffer = Some(device.create_buffer_init(
+                    &wgpu::util::BufferInitDescriptor {
+                        label: Some("batch index buffer"),
+                        contents: index_bytes,
+                        usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
+                    },
+                ));
+            }
+        }
+    }
+
+    /// Bind this batch's vertex/index buffers. Every entry in the batch shares these same buffers,
+    /// so this only needs to be called once per frame, before the loop of [Batch::draw_entry] calls
+    /// that follows — binding per-entry would reproduce exactly the per-input rebind cost batching
+    /// is meant to avoid.
+    pub fn bind<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        render_pass.set_vertex_buffer(
+            0,
+            self.vertex_buffer
+                .as_ref()
+                .expect("Batch::upload must be called before drawing")
+                .slice(..),
+        );
+        render_pass.set_index_buffer(
+            self.index_buffer
+                .as_ref()
+                .expect("Batch::upload must be called before drawing")
+                .slice(..),
+            wgpu::IndexFormat::Uint16,
+        );
+    }
+
+    /// Issue a `draw_indexed` for `entry`. The caller is responsible for calling [Batch::bind] once
+    /// before the first call, and for setting whatever bind group(s) that quad's input needs
+    /// beforehand.
+    pub fn draw_entry<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, entry: BatchEntry) {
+        render_pass.draw_indexed(entry.index_range.clone(), entry.base_vertex, 0..1);
+    }
+}
@@ -0,0 +1,152 @@
+//! Offscreen capture of the composited output: read a rendered frame back to the CPU as RGBA8, and
+//! either write sequential PNGs or accumulate frames into an animated GIF.
+
+use std::path::Path;
+
+/// A `COPY_SRC` render target the scene is drawn into so it can later be read back with
+/// [CaptureTarget::read_frame], alongside (or instead of) presenting to a surface.
+pub struct CaptureTarget {
+    texture: wgpu::Texture,
+    width: u32,
+    height: u32,
+}
+
+impl CaptureTarget {
+    pub fn new(device: &wgpu::Device, width: u32, height: u32) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("capture render target"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::COPY_SRC
+                | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+
+        Self {
+            texture,
+            width,
+            height,
+        }
+    }
+
+    pub fn view(&self) -> wgpu::TextureView {
+        self.texture.create_view(&wgpu::TextureViewDescriptor::default())
+    }
+
+    /// Copy the texture's current contents to a mapped readback buffer and return them as packed,
+    /// tightly-rowed RGBA8 bytes (`width * height * 4` long).
+    ///
+    /// This submits its own copy command and blocks (via `device.poll`) until the buffer is mapped,
+    /// so it should be called after the render pass that wrote this frame has been submitted.
+    pub fn read_frame(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> Vec<u8> {
+        // `bytes_per_row` in a texture-to-buffer copy must be a multiple of 256.
+        let unpadded_bytes_per_row = self.width * 4;
+        let padding = (256 - unpadded_bytes_per_row % 256) % 256;
+        let padded_bytes_per_row = unpadded_bytes_per_row + padding;
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("capture readback buffer"),
+            size: (padded_bytes_per_row * self.height) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("capture readback encoder"),
+        });
+
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(self.height),
+                },
+            },
+            wgpu::Extent3d {
+                width: self.width,
+                height: self.height,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            sender
+                .send(result)
+                .expect("readback map_async receiver dropped");
+        });
+        device.poll(wgpu::Maintain::Wait);
+        receiver
+            .recv()
+            .expect("readback map_async never resolved")
+            .expect("failed to map capture readback buffer");
+
+        let padded: Vec<u8> = slice.get_mapped_range().to_vec();
+        buffer.unmap();
+
+        // Strip the row padding back out so callers get tightly-packed RGBA8.
+        let mut unpadded = Vec::with_capacity((unpadded_bytes_per_row * self.height) as usize);
+        for row in padded.chunks(padded_bytes_per_row as usize) {
+            unpadded.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+        }
+        unpadded
+    }
+}
+
+/// Writes a single captured frame out as a PNG at `path`.
+pub fn write_png(
+    path: &Path,
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+) -> Result<(), image::ImageError> {
+    image::save_buffer(path, rgba, width, height, image::ColorType::Rgba8)
+}
+
+/// Accumulates captured frames and encodes them as an animated GIF.
+pub struct GifEncoder {
+    encoder: gif::Encoder<std::fs::File>,
+    width: u16,
+    height: u16,
+}
+
+impl GifEncoder {
+    /// Create a new GIF at `path` for frames of `width`x`height` RGBA8 pixels.
+    pub fn new(path: &Path, width: u32, height: u32) -> std::io::Result<Self> {
+        let width = width as u16;
+        let height = height as u16;
+        let file = std::fs::File::create(path)?;
+        let encoder = gif::Encoder::new(file, width, height, &[])?;
+        Ok(Self {
+            encoder,
+            width,
+            height,
+        })
+    }
+
+    /// Append one RGBA8 frame, held on screen for `delay_cs` centiseconds.
+    pub fn write_frame(&mut self, rgba: &mut [u8], delay_cs: u16) -> std::io::Result<()> {
+        let mut frame = gif::Frame::from_rgba_speed(self.width, self.height, rgba, 10);
+        frame.delay = delay_cs;
+        self.encoder.write_frame(&frame)
+    }
+}
@@ -0,0 +1,153 @@
+//! Static RGBA image inputs (logos, lower-thirds, watermarks), to sit alongside [super::videos::InputVideo]
+//! in the same scene without having to transcode the overlay to YUV first.
+
+use wgpu::util::DeviceExt;
+
+use super::Vertex;
+
+#[rustfmt::skip]
+const INDICES: [u16; 6] = [
+    0, 1, 3,
+    1, 2, 3
+];
+
+struct IndexBuffer {
+    buffer: wgpu::Buffer,
+    len: u32,
+}
+
+impl IndexBuffer {
+    fn new(device: &wgpu::Device, indices: &[u16]) -> Self {
+        Self {
+            buffer: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("image index buffer"),
+                contents: bytemuck::cast_slice(indices),
+                usage: wgpu::BufferUsages::INDEX,
+            }),
+            len: indices.len() as u32,
+        }
+    }
+}
+
+/// A still RGBA8 image drawn into the scene alongside YUV [super::videos::InputVideo]s. Decodes an
+/// encoded file (PNG, JPEG, ...) through the `image` crate once at construction and uploads it to a
+/// single `Rgba8UnormSrgb` texture, rather than a `YUVTextures`' three planes.
+pub struct InputImage {
+    // Kept alive for the lifetime of `bind_group`'s view; never read again after construction.
+    _texture: wgpu::Texture,
+    bind_group: wgpu::BindGroup,
+    vertices: wgpu::Buffer,
+    indices: IndexBuffer,
+}
+
+impl InputImage {
+    /// Decode the image at `path` and upload it, positioned at `position`.
+    pub fn from_file(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        path: &std::path::Path,
+        position: &[Vertex; 4],
+        bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> Result<Self, image::ImageError> {
+        let bytes = std::fs::read(path)?;
+        Self::from_bytes(device, queue, &bytes, position, bind_group_layout)
+    }
+
+    /// Decode an already-loaded encoded image from `bytes` and upload it, positioned at `position`.
+    pub fn from_bytes(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        bytes: &[u8],
+        position: &[Vertex; 4],
+        bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> Result<Self, image::ImageError> {
+        let image = image::load_from_memory(bytes)?;
+        let rgba = image.to_rgba8();
+        let (width, height) = (rgba.width(), rgba.height());
+
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("input image texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &rgba,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * width),
+                rows_per_image: Some(height),
+            },
+            size,
+        );
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("input image bind group"),
+            layout: bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+
+        let vertices = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("image vertex buffer"),
+            contents: bytemuck::cast_slice(position),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let indices = IndexBuffer::new(device, &INDICES);
+
+        Ok(Self {
+            _texture: texture,
+            bind_group,
+            vertices,
+            indices,
+        })
+    }
+}
+
+impl<'a> InputImage {
+    /// Draw this image. Follows the same `draw(render_pass, ...)` contract as
+    /// [super::videos::InputVideo::draw], so it drops into the existing render loop.
+    pub fn draw(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.set_index_buffer(self.indices.buffer.slice(..), wgpu::IndexFormat::Uint16);
+        render_pass.set_vertex_buffer(0, self.vertices.slice(..));
+        render_pass.draw_indexed(0..self.indices.len, 0, 0..1);
+    }
+}
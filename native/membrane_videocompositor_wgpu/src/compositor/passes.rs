@@ -0,0 +1,277 @@
+//! A lightweight render graph of named [Pass]es, so effects that need an intermediate target
+//! (blur, colour-grade, scaling to a shared canvas before the final composite) can be inserted
+//! without rewriting the draw loop. Each [Pass] declares the [TextureHandle]s it reads from and
+//! (if it isn't the terminal pass) the one it writes to; [PassGraph::run] resolves them in
+//! dependency order, allocating a transient texture per intermediate handle.
+//! [InputVideo::draw](super::videos::InputVideo::draw) becomes the body of the "composite" pass; a
+//! colour-conversion or effect pass can sit in front of it by declaring its own output handle and
+//! being added to the same [PassGraph].
+
+use std::collections::HashMap;
+
+use super::textures::YUVPlane;
+use super::videos::InputVideo;
+
+/// A handle identifying one of a [PassGraph]'s intermediate textures. Handles are only meaningful
+/// within the [PassGraph] that produced them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextureHandle(usize);
+
+/// The context a [Pass] is given when it runs: the device/queue, and the views produced by
+/// whichever passes it declared as its [Pass::inputs].
+pub struct PassContext<'a> {
+    pub device: &'a wgpu::Device,
+    pub queue: &'a wgpu::Queue,
+    views: &'a HashMap<TextureHandle, wgpu::TextureView>,
+}
+
+impl<'a> PassContext<'a> {
+    /// Look up the view produced by a pass this one declared as an input.
+    pub fn view(&self, handle: TextureHandle) -> &wgpu::TextureView {
+        self.views
+            .get(&handle)
+            .expect("pass read from a texture handle no earlier pass produced")
+    }
+
+    /// Every handle in this graph maps to a texture written by exactly one pass, so the first time
+    /// a pass writes to its target is always its only write this frame — there's nothing to
+    /// preserve underneath it, hence `Clear` rather than `Load`.
+    pub fn clear_ops(&self) -> wgpu::Operations<wgpu::Color> {
+        wgpu::Operations {
+            load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+            store: wgpu::StoreOp::Store,
+        }
+    }
+}
+
+/// One stage of a multi-pass frame: something that may need to prepare resources (bind groups,
+/// buffers) before recording its commands into a render pass targeting its own texture.
+pub trait Pass {
+    /// A human-readable name, surfaced in labels so passes are identifiable in a GPU debugger.
+    fn name(&self) -> &str;
+
+    /// The texture handles this pass reads from. Used to order passes topologically.
+    fn inputs(&self) -> &[TextureHandle];
+
+    /// The texture handle this pass writes to. `None` marks the terminal pass, which writes
+    /// directly to the graph's final output view instead of an intermediate texture.
+    fn output(&self) -> Option<TextureHandle>;
+
+    /// The `(width, height)` of the texture this pass produces, used to allocate it. Unused for
+    /// the terminal pass.
+    fn output_size(&self) -> (u32, u32);
+
+    /// Do any per-frame setup (e.g. writing uniforms) ahead of recording commands.
+    fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue);
+
+    /// Record this pass's draw calls into a render pass targeting `target`.
+    fn execute(&self, ctx: &PassContext, encoder: &mut wgpu::CommandEncoder, target: &wgpu::TextureView);
+}
+
+/// An unordered set of [Pass]es plus the dependency edges implied by their declared
+/// inputs/outputs, executed in topological order against a shared command encoder.
+///
+/// Generic over `'a` because passes typically borrow the inputs/resources they draw (e.g.
+/// [CompositePass] borrows a scene's [InputVideo]s) rather than owning them.
+pub struct PassGraph<'a> {
+    passes: Vec<Box<dyn Pass + 'a>>,
+    next_handle: usize,
+}
+
+impl<'a> Default for PassGraph<'a> {
+    fn default() -> Self {
+        Self {
+            passes: Vec::new(),
+            next_handle: 0,
+        }
+    }
+}
+
+impl<'a> PassGraph<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allocate a fresh, unique texture handle for a pass to declare as its output.
+    pub fn new_handle(&mut self) -> TextureHandle {
+        let handle = TextureHandle(self.next_handle);
+        self.next_handle += 1;
+        handle
+    }
+
+    /// Append a pass. Passes may be added in any order; [PassGraph::run] orders them by their
+    /// declared input/output dependencies.
+    pub fn add_pass(&mut self, pass: impl Pass + 'a) {
+        self.passes.push(Box::new(pass));
+    }
+
+    /// Topologically sort the graph's passes so that every pass runs after all passes producing
+    /// its inputs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the graph contains a cycle.
+    fn sorted_indices(&self) -> Vec<usize> {
+        let producer_of: HashMap<TextureHandle, usize> = self
+            .passes
+            .iter()
+            .enumerate()
+            .filter_map(|(i, pass)| pass.output().map(|handle| (handle, i)))
+            .collect();
+
+        let mut visited = vec![false; self.passes.len()];
+        let mut in_progress = vec![false; self.passes.len()];
+        let mut order = Vec::with_capacity(self.passes.len());
+
+        fn visit(
+            i: usize,
+            passes: &[Box<dyn Pass + '_>],
+            producer_of: &HashMap<TextureHandle, usize>,
+            visited: &mut [bool],
+            in_progress: &mut [bool],
+            order: &mut Vec<usize>,
+        ) {
+            if visited[i] {
+                return;
+            }
+            assert!(!in_progress[i], "render graph contains a cycle");
+            in_progress[i] = true;
+
+            for input in passes[i].inputs() {
+                if let Some(&producer) = producer_of.get(input) {
+                    visit(producer, passes, producer_of, visited, in_progress, order);
+                }
+            }
+
+            in_progress[i] = false;
+            visited[i] = true;
+            order.push(i);
+        }
+
+        for i in 0..self.passes.len() {
+            visit(
+                i,
+                &self.passes,
+                &producer_of,
+                &mut visited,
+                &mut in_progress,
+                &mut order,
+            );
+        }
+
+        order
+    }
+
+    /// Prepare and execute every pass in dependency order. Intermediate passes (`output() ==
+    /// Some(_)`) get a transient texture allocated fresh for this frame; the terminal pass
+    /// (`output() == None`) writes to `final_view` instead.
+    pub fn run(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        final_view: &wgpu::TextureView,
+    ) {
+        for pass in self.passes.iter_mut() {
+            pass.prepare(device, queue);
+        }
+
+        let order = self.sorted_indices();
+
+        // Keep the owning textures alive alongside the views borrowed from them.
+        let mut textures: HashMap<TextureHandle, wgpu::Texture> = HashMap::new();
+        let mut views: HashMap<TextureHandle, wgpu::TextureView> = HashMap::new();
+
+        for i in order {
+            let pass = &self.passes[i];
+
+            let target = match pass.output() {
+                Some(handle) => {
+                    let (width, height) = pass.output_size();
+                    let texture = device.create_texture(&wgpu::TextureDescriptor {
+                        label: Some(pass.name()),
+                        size: wgpu::Extent3d {
+                            width,
+                            height,
+                            depth_or_array_layers: 1,
+                        },
+                        mip_level_count: 1,
+                        sample_count: 1,
+                        dimension: wgpu::TextureDimension::D2,
+                        format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                        usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                            | wgpu::TextureUsages::TEXTURE_BINDING,
+                        view_formats: &[],
+                    });
+                    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+                    textures.insert(handle, texture);
+                    views.insert(handle, view);
+                    views.get(&handle).expect("just inserted")
+                }
+                None => final_view,
+            };
+
+            let ctx = PassContext {
+                device,
+                queue,
+                views: &views,
+            };
+            pass.execute(&ctx, encoder, target);
+        }
+    }
+}
+
+/// The final scene-composition pass: draws every visible [InputVideo] over the target view. This
+/// is exactly what the render loop used to do directly; wrapping it as a [Pass] just lets other
+/// passes (conversion, effects) sit in front of it in the same [PassGraph].
+pub struct CompositePass<'a> {
+    inputs: Vec<(&'a InputVideo, YUVPlane)>,
+}
+
+impl<'a> CompositePass<'a> {
+    pub fn new(inputs: Vec<(&'a InputVideo, YUVPlane)>) -> Self {
+        Self { inputs }
+    }
+}
+
+impl<'a> Pass for CompositePass<'a> {
+    fn name(&self) -> &str {
+        "composite"
+    }
+
+    fn inputs(&self) -> &[TextureHandle] {
+        &[]
+    }
+
+    fn output(&self) -> Option<TextureHandle> {
+        // Terminal pass: writes to the graph's final output view.
+        None
+    }
+
+    fn output_size(&self) -> (u32, u32) {
+        (0, 0)
+    }
+
+    fn prepare(&mut self, _device: &wgpu::Device, _queue: &wgpu::Queue) {
+        // Nothing to prepare: each InputVideo owns its own vertex/instance/transform buffers and
+        // keeps them up to date via `upload_data`/`set_transform`/`set_instances`.
+    }
+
+    fn execute(&self, ctx: &PassContext, encoder: &mut wgpu::CommandEncoder, target: &wgpu::TextureView) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("composite pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: ctx.clear_ops(),
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        for (input, plane) in &self.inputs {
+            input.draw(&mut render_pass, *plane);
+        }
+    }
+}
@@ -1,14 +1,95 @@
+use std::cell::Cell;
+
 use wgpu::util::DeviceExt;
 
+use super::batch::{Batch, BatchEntry};
 use super::textures::{YUVPlane, YUVTextures};
 use super::Vertex;
 
+/// OpenGL's clip space has `z` in `[-1, 1]`; wgpu's has it in `[0, 1]`. Composing this into a
+/// video's transform keeps `cgmath`-built projection/view/model matrices working unmodified.
+#[rustfmt::skip]
+pub const OPENGL_TO_WGPU_MATRIX: cgmath::Matrix4<f32> = cgmath::Matrix4::new(
+    1.0, 0.0, 0.0, 0.0,
+    0.0, 1.0, 0.0, 0.0,
+    0.0, 0.0, 0.5, 0.0,
+    0.0, 0.0, 0.5, 1.0,
+);
+
+/// The per-input transform uniform, holding a `cgmath::Matrix4` applied to the base quad in the
+/// vertex shader. Writing a new transform is one 64-byte `write_buffer` call, rather than
+/// recreating the vertex buffer as layout changes (move/scale/crop) used to require.
+struct TransformUniform {
+    buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+impl TransformUniform {
+    fn new(device: &wgpu::Device, bind_group_layout: &wgpu::BindGroupLayout) -> Self {
+        let raw: [[f32; 4]; 4] = OPENGL_TO_WGPU_MATRIX.into();
+
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("video transform uniform buffer"),
+            contents: bytemuck::cast_slice(&raw),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("video transform bind group"),
+            layout: bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+
+        Self { buffer, bind_group }
+    }
+
+    fn set(&self, queue: &wgpu::Queue, transform: cgmath::Matrix4<f32>) {
+        let raw: [[f32; 4]; 4] = (OPENGL_TO_WGPU_MATRIX * transform).into();
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&raw));
+    }
+}
+
 #[rustfmt::skip]
 const INDICES: [u16; 6] = [
-    0, 1, 3, 
+    0, 1, 3,
     1, 2, 3
 ];
 
+/// A per-instance transform for drawing a single [InputVideo] at several layout positions in one
+/// `draw_indexed` call: scale and offset applied to the base quad in clip space.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct InstanceRaw {
+    pub scale: [f32; 2],
+    pub offset: [f32; 2],
+}
+
+impl InstanceRaw {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 2] =
+        wgpu::vertex_attr_array![1 => Float32x2, 2 => Float32x2];
+
+    /// The vertex buffer layout for slot 1, stepping once per instance rather than once per vertex.
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<InstanceRaw>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+impl Default for InstanceRaw {
+    fn default() -> Self {
+        Self {
+            scale: [1.0, 1.0],
+            offset: [0.0, 0.0],
+        }
+    }
+}
+
 struct IndexBuffer {
     buffer: wgpu::Buffer,
     len: u32,
@@ -27,19 +108,50 @@ impl IndexBuffer {
     }
 }
 
+struct InstanceBuffer {
+    buffer: wgpu::Buffer,
+    capacity: usize,
+    /// How many of `capacity` instances are currently populated. A `Cell` because
+    /// [InputVideo::set_instances] only takes `&self`, matching [InputVideo::upload_data].
+    len: Cell<u32>,
+}
+
+impl InstanceBuffer {
+    fn new(device: &wgpu::Device, capacity: usize) -> Self {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("video instance buffer"),
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            size: (capacity * std::mem::size_of::<InstanceRaw>()) as u64,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            buffer,
+            capacity,
+            len: Cell::new(0),
+        }
+    }
+}
+
 pub struct InputVideo {
     textures: YUVTextures,
+    quad: [Vertex; 4],
     vertices: wgpu::Buffer,
     indices: IndexBuffer,
+    instances: InstanceBuffer,
+    transform: TransformUniform,
 }
 
 impl InputVideo {
     pub fn new(
         device: &wgpu::Device,
+        queue: &wgpu::Queue,
         width: u32,
         height: u32,
         position: &[Vertex; 4],
         texture_bind_group_layout: &wgpu::BindGroupLayout,
+        transform_bind_group_layout: &wgpu::BindGroupLayout,
+        max_instances: usize,
     ) -> Self {
         let textures = YUVTextures::new(
             device,
@@ -57,23 +169,87 @@ impl InputVideo {
 
         let indices = IndexBuffer::new(device, &INDICES);
 
-        Self {
+        let instances = InstanceBuffer::new(device, max_instances.max(1));
+        let transform = TransformUniform::new(device, transform_bind_group_layout);
+
+        let input_video = Self {
             textures,
+            quad: *position,
             vertices,
             indices,
-        }
+            instances,
+            transform,
+        };
+
+        // A single identity instance, so `draw` behaves exactly as before until `set_instances`
+        // is called.
+        input_video.set_instances(queue, &[InstanceRaw::default()]);
+
+        input_video
     }
 
     pub fn upload_data(&self, queue: &wgpu::Queue, data: &[u8]) {
         self.textures.upload_data(queue, data);
     }
+
+    /// Set the per-instance transforms this video is drawn with. A scene that shows the same input
+    /// at several layout positions can pass one [InstanceRaw] per position instead of issuing one
+    /// draw call per copy.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `instances` is empty or longer than the `max_instances` this video was created
+    /// with.
+    pub fn set_instances(&self, queue: &wgpu::Queue, instances: &[InstanceRaw]) {
+        assert!(!instances.is_empty(), "a video needs at least one instance");
+        assert!(
+            instances.len() <= self.instances.capacity,
+            "tried to set {} instances but this video only has room for {}",
+            instances.len(),
+            self.instances.capacity
+        );
+
+        queue.write_buffer(&self.instances.buffer, 0, bytemuck::cast_slice(instances));
+        self.instances.len.set(instances.len() as u32);
+    }
+
+    /// Set the transform (translation/scale/rotation, typically built with `cgmath`) applied to
+    /// this video's quad in the vertex shader. Animating an input's position or size across frames
+    /// only needs to write the 64 bytes of this matrix, rather than reallocating geometry.
+    pub fn set_transform(&self, queue: &wgpu::Queue, transform: cgmath::Matrix4<f32>) {
+        self.transform.set(queue, transform);
+    }
 }
 
 impl<'a> InputVideo {
+    /// Draw this input on its own, rebinding its own vertex/index buffers. Kept as a fallback for
+    /// callers that don't batch (e.g. drawing a single input in isolation); [InputVideo::push_into_batch]
+    /// is the default path for scenes with many inputs.
     pub fn draw(&'a self, render_pass: &mut wgpu::RenderPass<'a>, plane: YUVPlane) {
         render_pass.set_bind_group(0, self.textures[plane].bind_group.as_ref().unwrap(), &[]);
+        render_pass.set_bind_group(1, &self.transform.bind_group, &[]);
         render_pass.set_index_buffer(self.indices.buffer.slice(..), wgpu::IndexFormat::Uint16);
         render_pass.set_vertex_buffer(0, self.vertices.slice(..));
-        render_pass.draw_indexed(0..self.indices.len, 0, 0..1);
+        render_pass.set_vertex_buffer(1, self.instances.buffer.slice(..));
+        render_pass.draw_indexed(0..self.indices.len, 0, 0..self.instances.len.get());
+    }
+
+    /// Push this input's quad into a shared [Batch], returning the [BatchEntry] needed to draw it
+    /// once the batch has been uploaded. This is how a layout with many inputs scales with buffer
+    /// uploads rather than with bind-group/buffer-bind churn: one vertex and one index buffer are
+    /// shared across every input instead of each getting their own.
+    pub fn push_into_batch(&self, batch: &mut Batch) -> BatchEntry {
+        batch.push_quad(self.quad)
+    }
+
+    /// The bind group for `plane`, to be set before [Batch::draw_entry] draws this input's entry.
+    pub fn bind_group(&self, plane: YUVPlane) -> &wgpu::BindGroup {
+        self.textures[plane].bind_group.as_ref().unwrap()
+    }
+
+    /// This input's transform bind group, to be set (at bind group index 1) before
+    /// [Batch::draw_entry] draws this input's entry.
+    pub fn transform_bind_group(&self) -> &wgpu::BindGroup {
+        &self.transform.bind_group
     }
 }
\ No newline at end of file